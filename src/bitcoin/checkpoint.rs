@@ -1,18 +1,20 @@
 use super::{
     adapter::Adapter,
     header_queue::HeaderQueue,
-    signatory::SignatorySet,
+    signatory::{Signatory, SignatorySet},
     threshold_sig::{Pubkey, Signature, ThresholdSig},
     ConsensusKey, Xpub,
 };
 use crate::error::{Error, Result};
-use bitcoin::hashes::Hash;
+use bitcoin::hashes::{sha256d, Hash};
 use bitcoin::Txid;
 use orga::{
     call::Call,
     client::Client,
     collections::{ChildMut, Deque, Map, Ref},
+    context::Context,
     encoding::{Decode, Encode},
+    plugins::Time,
     query::Query,
     state::State,
     Error as OrgaError, Result as OrgaResult,
@@ -20,11 +22,40 @@ use orga::{
 
 pub const INITIAL_QUORUM_PERCENT: u64 = 70;
 
+/// How often, in seconds, a building checkpoint with at least one output
+/// advances to signing.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 60 * 60 * 4;
+
+/// How long, in seconds, a checkpoint may remain `Signing` without
+/// reaching quorum before it is timed out and signing is restarted
+/// against a freshly-derived signatory set.
+pub const DEFAULT_SIGNING_TIMEOUT: u64 = 60 * 60 * 8;
+
+/// The fraction of voting power an alternative (non-canonical) sighash
+/// must accumulate before a signing checkpoint is considered divergent
+/// rather than simply still in progress.
+pub const DEFAULT_DIVERGENCE_QUORUM_PERCENT: u64 = 34;
+
+/// The message signatories are expected to sign for a checkpoint.
+///
+/// This stands in for the checkpoint transaction's real Bitcoin sighash
+/// until transaction construction (see the `script_pubkey` TODO on
+/// `Input`) is implemented.
+pub type Sighash = sha256d::Hash;
+
 #[derive(Debug, Encode, Decode)]
 pub enum CheckpointStatus {
     Building,
     Signing,
     Complete,
+    /// Signatories did not agree on the message being signed: an
+    /// alternative sighash accumulated enough power that the canonical
+    /// one can no longer be assumed to reach quorum.
+    Diverged,
+    /// Signing was aborted after exceeding the signing deadline without
+    /// reaching quorum. A fresh checkpoint picks up where this one left
+    /// off, re-signing against a newly-derived signatory set.
+    TimedOut,
 }
 
 impl Default for CheckpointStatus {
@@ -84,6 +115,26 @@ pub struct Output {
     pub script: Vec<u8>,
 }
 
+/// The accumulated voting power and backing signatories behind a single
+/// sighash that signatories have endorsed while a checkpoint is signing.
+#[derive(State, Call, Query, Client)]
+pub struct SighashTally {
+    pub sighash: Adapter<Sighash>,
+    pub voting_power: u64,
+    pub signatories: Deque<Pubkey>,
+}
+
+/// A stake-weighted snapshot of how close the signing checkpoint is to
+/// quorum: voting power signed so far, total and quorum voting power, and
+/// the signatories who have not yet signed.
+#[derive(Debug, Encode, Decode)]
+pub struct SigningProgress {
+    pub signed_voting_power: u64,
+    pub total_voting_power: u64,
+    pub quorum_voting_power: u64,
+    pub remaining_signatories: Vec<Pubkey>,
+}
+
 #[derive(State, Call, Query, Client)]
 pub struct Checkpoint {
     pub status: CheckpointStatus,
@@ -91,12 +142,221 @@ pub struct Checkpoint {
     pub outputs: Deque<Output>,
     sig: ThresholdSig,
     pub sigset: SignatorySet,
+    /// The unix timestamp (seconds) this checkpoint entered `Building`,
+    /// used to determine when it should advance to `Signing`.
+    pub created_at: i64,
+    /// The unix timestamp (seconds) this checkpoint entered `Signing`,
+    /// used to detect a stuck checkpoint that never reaches quorum.
+    pub signing_started_at: i64,
+    /// The sighash each signatory has most recently endorsed, so a
+    /// signatory's voting power is only ever counted toward one tally.
+    signatory_sighash: Map<Pubkey, Adapter<Sighash>>,
+    /// Tracks, for each distinct sighash a signatory has endorsed while
+    /// this checkpoint is signing, the voting power and signatories
+    /// backing it. Lets a divergent ("split-brain") checkpoint be
+    /// diagnosed instead of stalling silently.
+    pub sighash_tallies: Deque<SighashTally>,
+}
+
+impl Checkpoint {
+    /// The sighash every signatory is expected to sign: the checkpoint
+    /// transaction built deterministically from `inputs`, `outputs`, and
+    /// `sigset`.
+    ///
+    /// TODO: derive this from the real checkpoint Bitcoin transaction once
+    /// transaction construction lands; until then, hash the checkpoint's
+    /// contents so signatories still commit to identical data.
+    pub fn sighash(&self) -> Result<Adapter<Sighash>> {
+        let mut engine = Sighash::engine();
+
+        for i in 0..self.inputs.len() {
+            let input = self.inputs.get(i)?.unwrap();
+            engine.input(&input.txid.encode()?);
+            engine.input(&input.vout.to_be_bytes());
+        }
+
+        for i in 0..self.outputs.len() {
+            let output = self.outputs.get(i)?.unwrap();
+            engine.input(&output.amount.to_be_bytes());
+            engine.input(&output.script);
+        }
+
+        engine.input(&self.sigset.encode()?);
+
+        Ok(Adapter::new(Sighash::from_engine(engine)))
+    }
+
+    /// Records that `pubkey` (backed by `voting_power`) has endorsed
+    /// `sighash`, moving their power off any sighash they previously
+    /// endorsed so each signatory is only ever counted once.
+    fn record_endorsement(
+        &mut self,
+        pubkey: Pubkey,
+        sighash: Adapter<Sighash>,
+        voting_power: u64,
+    ) -> Result<()> {
+        if let Some(prev) = self.signatory_sighash.get(pubkey.clone())? {
+            if *prev == sighash {
+                return Ok(());
+            }
+            let prev = prev.clone();
+            self.remove_tally_vote(&prev, voting_power)?;
+        }
+
+        self.signatory_sighash.insert(pubkey.clone(), sighash.clone())?;
+        self.add_tally_vote(sighash, pubkey, voting_power)
+    }
+
+    fn add_tally_vote(
+        &mut self,
+        sighash: Adapter<Sighash>,
+        pubkey: Pubkey,
+        voting_power: u64,
+    ) -> Result<()> {
+        for i in 0..self.sighash_tallies.len() {
+            let mut tally = self.sighash_tallies.get_mut(i)?.unwrap();
+            if tally.sighash == sighash {
+                tally.voting_power += voting_power;
+                tally.signatories.push_back(pubkey)?;
+                return Ok(());
+            }
+        }
+
+        let mut tally = SighashTally {
+            sighash,
+            voting_power,
+            signatories: Deque::default(),
+        };
+        tally.signatories.push_back(pubkey)?;
+        self.sighash_tallies.push_back(tally)?;
+
+        Ok(())
+    }
+
+    fn remove_tally_vote(&mut self, sighash: &Adapter<Sighash>, voting_power: u64) -> Result<()> {
+        for i in 0..self.sighash_tallies.len() {
+            let mut tally = self.sighash_tallies.get_mut(i)?.unwrap();
+            if &tally.sighash == sighash {
+                tally.voting_power = tally.voting_power.saturating_sub(voting_power);
+                // TODO: drop the signatory from `tally.signatories` once
+                // `Deque` supports removing an arbitrary element. Until
+                // then, `divergent_sighashes` cross-checks against
+                // `signatory_sighash` to filter out signatories who have
+                // since switched their vote away from this sighash.
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the competing sighashes signatories have endorsed, other
+    /// than `canonical`, along with the voting power backing each and the
+    /// signatories responsible, so relayers can surface the fault instead
+    /// of silently stalling.
+    pub fn divergent_sighashes(
+        &self,
+        canonical: &Adapter<Sighash>,
+    ) -> Result<Vec<(Adapter<Sighash>, u64, Vec<Pubkey>)>> {
+        let mut out = vec![];
+
+        for i in 0..self.sighash_tallies.len() {
+            let tally = self.sighash_tallies.get(i)?.unwrap();
+            if &tally.sighash == canonical {
+                continue;
+            }
+
+            // `tally.signatories` only ever grows (`Deque` can't drop an
+            // arbitrary element - see `remove_tally_vote`), so a
+            // signatory who has since switched their vote away and back
+            // again may be listed more than once, and one who switched
+            // away for good may still be listed at all. `signatory_sighash`
+            // is the authoritative record of each signatory's current
+            // vote, so cross-check against it and dedupe to report only
+            // those still actually backing this sighash, once each.
+            let mut signatories = vec![];
+            for j in 0..tally.signatories.len() {
+                let pubkey = tally.signatories.get(j)?.unwrap().clone();
+                let still_backing = match self.signatory_sighash.get(pubkey.clone())? {
+                    Some(current) => *current == tally.sighash,
+                    None => false,
+                };
+                if still_backing && !signatories.contains(&pubkey) {
+                    signatories.push(pubkey);
+                }
+            }
+
+            // Every signatory behind this sighash has since switched away,
+            // so it is no longer a competing sighash.
+            if signatories.is_empty() {
+                continue;
+            }
+
+            out.push((tally.sighash.clone(), tally.voting_power, signatories));
+        }
+
+        Ok(out)
+    }
+
+    /// The txid of this checkpoint's transaction once broadcast to
+    /// Bitcoin, used to cross-reference it against the `HeaderQueue`.
+    ///
+    /// TODO: derive this from the real checkpoint transaction once
+    /// transaction construction lands; until then it reuses `sighash`,
+    /// since both stand in for the same not-yet-built transaction.
+    pub fn txid(&self) -> Result<Adapter<Txid>> {
+        let sighash = self.sighash()?;
+        Ok(Adapter::new(Txid::from(*sighash)))
+    }
+}
+
+fn voting_power(sigset: &SignatorySet, pubkey: &Pubkey) -> u64 {
+    sigset
+        .iter()
+        .find(|s| &s.pubkey == pubkey)
+        .map(|s| s.voting_power)
+        .unwrap_or(0)
+}
+
+fn total_voting_power(sigset: &SignatorySet) -> u64 {
+    sigset.iter().map(|s| s.voting_power).sum()
+}
+
+/// The signatures in `sig` contributed by signatories who are still part
+/// of `new_sigset`, for carrying progress over into a freshly-rebuilt
+/// `ThresholdSig` after a re-rotation.
+fn carry_over_signatures(
+    sig: &ThresholdSig,
+    new_sigset: &SignatorySet,
+) -> Result<Vec<(Pubkey, Signature)>> {
+    Ok(sig
+        .iter()?
+        .filter(|(pubkey, _)| new_sigset.iter().any(|s| &s.pubkey == pubkey))
+        .collect())
+}
+
+fn now_seconds() -> Result<i64> {
+    let time = Context::resolve::<Time>()
+        .ok_or_else(|| Error::Orga(OrgaError::App("No time context available".to_string())))?;
+    Ok(time.seconds)
 }
 
 #[derive(State, Call, Query, Client)]
 pub struct CheckpointQueue {
     queue: Deque<Checkpoint>,
     index: u32,
+    /// How often, in seconds, a building checkpoint with at least one
+    /// output advances to signing. Configurable so networks can tune
+    /// checkpoint cadence without a code change.
+    pub checkpoint_interval: u64,
+    /// How long, in seconds, a checkpoint may remain `Signing` without
+    /// reaching quorum before it is timed out and restarted.
+    pub signing_timeout: u64,
+    /// The fraction of voting power an alternative (non-canonical) sighash
+    /// must accumulate before a signing checkpoint is considered divergent
+    /// rather than simply still in progress. Configurable so networks can
+    /// tune divergence sensitivity without a code change.
+    pub divergence_quorum_percent: u64,
 }
 
 pub struct CompletedCheckpoint<'a>(Ref<'a, Checkpoint>);
@@ -172,6 +432,13 @@ impl CheckpointQueue {
         for i in 0..self.queue.len() {
             let checkpoint = self.queue.get(i)?.unwrap();
 
+            // A `TimedOut` checkpoint is resolved, not still in progress -
+            // its replacement picks up right after it in the queue, so it
+            // shouldn't gate later checkpoints from being reported complete.
+            if matches!(checkpoint.status, CheckpointStatus::TimedOut) {
+                continue;
+            }
+
             if !matches!(checkpoint.status, CheckpointStatus::Complete) {
                 break;
             }
@@ -182,6 +449,11 @@ impl CheckpointQueue {
         Ok(out)
     }
 
+    /// The checkpoint currently being signed, whether or not its
+    /// signatories have diverged. A `Diverged` checkpoint still needs to
+    /// be reachable here so a late canonical-quorum signature can
+    /// complete it, so its divergence can be queried, and so it can be
+    /// timed out like any other stuck signing checkpoint.
     #[query]
     pub fn signing(&self) -> Result<Option<SigningCheckpoint<'_>>> {
         if self.queue.len() < 2 {
@@ -189,7 +461,10 @@ impl CheckpointQueue {
         }
 
         let second = self.get(self.index - 1)?;
-        if !matches!(second.status, CheckpointStatus::Signing) {
+        if !matches!(
+            second.status,
+            CheckpointStatus::Signing | CheckpointStatus::Diverged
+        ) {
             return Ok(None);
         }
 
@@ -202,7 +477,10 @@ impl CheckpointQueue {
         }
 
         let second = self.get_mut(self.index - 1)?;
-        if !matches!(second.status, CheckpointStatus::Signing) {
+        if !matches!(
+            second.status,
+            CheckpointStatus::Signing | CheckpointStatus::Diverged
+        ) {
             return Ok(None);
         }
 
@@ -229,25 +507,154 @@ impl CheckpointQueue {
                     return Ok(());
                 }
 
+                if self.checkpoint_interval == 0 {
+                    self.checkpoint_interval = DEFAULT_CHECKPOINT_INTERVAL;
+                }
+
+                if self.signing_timeout == 0 {
+                    self.signing_timeout = DEFAULT_SIGNING_TIMEOUT;
+                }
+
+                if self.divergence_quorum_percent == 0 {
+                    self.divergence_quorum_percent = DEFAULT_DIVERGENCE_QUORUM_PERCENT;
+                }
+
                 self.push_building(sig_keys)?;
+                return Ok(());
+            }
+
+            if self.maybe_time_out_signing(sig_keys)? {
+                return Ok(());
+            }
+
+            self.start_signing(sig_keys)?;
+        }
+
+        Ok(())
+    }
+
+    /// If a checkpoint has been `Signing` for longer than
+    /// `signing_timeout` without reaching quorum, marks it `TimedOut` and
+    /// restarts signing against a freshly-derived signatory set, carrying
+    /// over signatures from signatories who remain in the new set.
+    /// Returns whether a timeout was processed.
+    fn maybe_time_out_signing(&mut self, sig_keys: &Map<ConsensusKey, Xpub>) -> Result<bool> {
+        let signing = match self.signing()? {
+            Some(signing) => signing,
+            None => return Ok(false),
+        };
+
+        let now = now_seconds()?;
+        let overdue = now - signing.0.signing_started_at > self.signing_timeout as i64;
+        drop(signing);
+
+        if !overdue {
+            return Ok(false);
+        }
+
+        self.time_out_signing(sig_keys)?;
+        Ok(true)
+    }
+
+    fn time_out_signing(&mut self, sig_keys: &Map<ConsensusKey, Xpub>) -> Result<()> {
+        let new_sigset = SignatorySet::from_validator_ctx(self.index, sig_keys)?;
+
+        let mut signing = self
+            .signing_mut()?
+            .ok_or_else(|| Error::Orga(OrgaError::App("No checkpoint to time out".to_string())))?;
+
+        // Keep signatures from signatories still present in the new set.
+        let carried_over = carry_over_signatures(&signing.0.sig, &new_sigset)?;
+
+        // Same for the reserve input's signatures.
+        let reserve_carried_over = match signing.0.inputs.get(0)? {
+            Some(reserve_in) => carry_over_signatures(&reserve_in.sig, &new_sigset)?,
+            None => vec![],
+        };
+
+        let mut inputs = signing.0.inputs.clone();
+        let outputs = signing.0.outputs.clone();
+
+        // Record the stuck attempt as timed out rather than silently
+        // discarding it, so clients can tell it apart from a normal
+        // completion.
+        signing.0.status = CheckpointStatus::TimedOut;
+        drop(signing);
+
+        let building = self
+            .queue
+            .pop_back()?
+            .ok_or_else(|| Error::Orga(OrgaError::App("Checkpoint queue is empty".to_string())))?;
+
+        // Re-wire the reserve input's threshold signature against the
+        // freshly-derived signatory set, the same way `start_signing`
+        // sets it up - otherwise it would keep tracking quorum against
+        // the stale, pre-rotation set.
+        if let Some(mut reserve_in) = inputs.get_mut(0)? {
+            reserve_in.sig.set_up(new_sigset.clone())?;
+            for (pubkey, sig) in reserve_carried_over {
+                reserve_in.sig.sign(pubkey, sig)?;
             }
+        }
 
-            // TODO: advance to signing and push new building after time has passed
+        let now = now_seconds()?;
+        self.queue.push_back(Default::default())?;
+        let mut restarted = self.get_mut(self.index)?;
+        restarted.inputs = inputs;
+        restarted.outputs = outputs;
+        restarted.status = CheckpointStatus::Signing;
+        restarted.created_at = now;
+        restarted.signing_started_at = now;
+        restarted.sigset = new_sigset.clone();
+        restarted.sig.set_up(new_sigset)?;
+        for (pubkey, sig) in carried_over {
+            restarted.sig.sign(pubkey, sig)?;
         }
+        drop(restarted);
+
+        self.index += 1;
+        self.queue.push_back(building)?;
 
         Ok(())
     }
 
-    // fn start_signing(&mut self) -> Result<()> {
-    //     if self.signing()?.is_some() {
-    //         return Err(OrgaError::App("Previous checkpoint is still being signed".to_string()).into());
-    //     }
+    fn start_signing(&mut self, sig_keys: &Map<ConsensusKey, Xpub>) -> Result<()> {
+        // Never advance while a previous checkpoint is still signing -
+        // there must only ever be one checkpoint in `Signing` at a time.
+        if self.signing()?.is_some() {
+            return Ok(());
+        }
+
+        let now = now_seconds()?;
+        let building = self.building()?;
+        let has_outputs = building.0.outputs.len() > 0;
+        let age = now - building.0.created_at;
+        let sigset = building.0.sigset.clone();
+        drop(building);
 
-    //     let mut building = self.building_mut()?;
-    //     building.0.status = CheckpointStatus::Signing;
+        if !has_outputs || (age as u64) < self.checkpoint_interval {
+            return Ok(());
+        }
 
-    //     self.push_building()
-    // }
+        let mut building = self.building_mut()?;
+        building.0.status = CheckpointStatus::Signing;
+        building.0.signing_started_at = now;
+
+        // Wire up the checkpoint's own threshold signature against the
+        // signatory set that is now signing - without this, `sig` never
+        // learns the quorum/signatories it needs for `sign()`/`done()`/
+        // `signed()`/`iter()` to work.
+        building.0.sig.set_up(sigset.clone())?;
+
+        // Wire up the reserve input's threshold signature the same way,
+        // so its voting power and quorum can be tracked too.
+        if let Some(mut reserve_in) = building.0.inputs.get_mut(0)? {
+            reserve_in.sig.set_up(sigset)?;
+        }
+        drop(building);
+
+        self.push_building(sig_keys)
+    }
 
     fn push_building(&mut self, sig_keys: &Map<ConsensusKey, Xpub>) -> Result<()> {
         let index = self.index;
@@ -258,8 +665,10 @@ impl CheckpointQueue {
         #[cfg(feature = "full")]
         let sigset = SignatorySet::from_validator_ctx(index, sig_keys)?;
 
+        let now = now_seconds()?;
         self.queue.push_back(Default::default())?;
         let mut building = self.building_mut()?;
+        building.0.created_at = now;
 
         #[cfg(feature = "full")]
         {
@@ -271,12 +680,34 @@ impl CheckpointQueue {
 
     // #[call]
     // TODO: should have N signatures (1 per spent input of checkpoint)
-    pub fn sign_checkpoint(&mut self, pubkey: Pubkey, sig: Signature) -> Result<()> {
+    pub fn sign_checkpoint(
+        &mut self,
+        pubkey: Pubkey,
+        sig: Signature,
+        sighash: Adapter<Sighash>,
+    ) -> Result<()> {
+        let divergence_quorum_percent = self.divergence_quorum_percent;
+
         let mut signing = self
             .signing_mut()?
             .ok_or_else(|| Error::Orga(OrgaError::App("No checkpoint to be signed".to_string())))?;
 
-        signing.0.sig.sign(pubkey, sig)?;
+        verify_sig(&pubkey, &sig, &sighash)?;
+
+        let vp = voting_power(&signing.0.sigset, &pubkey);
+        if vp == 0 {
+            return Err(Error::Orga(OrgaError::App(
+                "Pubkey is not part of the signing checkpoint's signatory set".to_string(),
+            )));
+        }
+        signing
+            .0
+            .record_endorsement(pubkey.clone(), sighash.clone(), vp)?;
+
+        let canonical_sighash = signing.0.sighash()?;
+        if sighash == canonical_sighash {
+            signing.0.sig.sign(pubkey, sig)?;
+        }
 
         if signing.0.sig.done() {
             // TODO: move this block into its own method
@@ -292,13 +723,388 @@ impl CheckpointQueue {
             reserve_in.vout = 0;
             // TODO: reserve_in.script_pubkey = InputType::Reserve;
             // TODO: reserve_in.sig.set_up(sig_set)?;
+        } else if !matches!(signing.0.status, CheckpointStatus::Diverged) {
+            let total_vp = total_voting_power(&signing.0.sigset);
+            let diverged = signing
+                .0
+                .divergent_sighashes(&canonical_sighash)?
+                .into_iter()
+                .any(|(_, vp, _)| vp * 100 >= total_vp * divergence_quorum_percent);
+
+            if diverged {
+                signing.0.status = CheckpointStatus::Diverged;
+            }
         }
 
         Ok(())
     }
 
+    /// Returns the competing sighashes signers have endorsed for the
+    /// checkpoint currently being signed, along with the voting power
+    /// backing each and the responsible signatories, so relayers can
+    /// surface a divergent checkpoint instead of waiting on it forever.
+    #[query]
+    pub fn signing_divergence(&self) -> Result<Vec<(Adapter<Sighash>, u64, Vec<Pubkey>)>> {
+        let signing = match self.signing()? {
+            Some(signing) => signing,
+            None => return Ok(vec![]),
+        };
+
+        let canonical_sighash = signing.0.sighash()?;
+        signing.0.divergent_sighashes(&canonical_sighash)
+    }
+
+    /// Reports how close the checkpoint currently being signed is to
+    /// completion: the voting power that has signed so far, the
+    /// signatory set's total voting power, the quorum threshold, and the
+    /// signatories who have not yet signed. Returns `None` if there is no
+    /// checkpoint being signed.
+    #[query]
+    pub fn signing_progress(&self) -> Result<Option<SigningProgress>> {
+        let signing = match self.signing()? {
+            Some(signing) => signing,
+            None => return Ok(None),
+        };
+
+        let total_voting_power = total_voting_power(&signing.0.sigset);
+        let quorum_voting_power = total_voting_power * INITIAL_QUORUM_PERCENT / 100;
+
+        let mut signed_voting_power = 0;
+        let mut remaining_signatories = vec![];
+
+        for signatory in signing.0.sigset.iter() {
+            if signing.0.sig.signed(&signatory.pubkey)? {
+                signed_voting_power += signatory.voting_power;
+            } else {
+                remaining_signatories.push(signatory.pubkey.clone());
+            }
+        }
+
+        Ok(Some(SigningProgress {
+            signed_voting_power,
+            total_voting_power,
+            quorum_voting_power,
+            remaining_signatories,
+        }))
+    }
+
     #[query]
     pub fn active_sigset(&self) -> Result<SignatorySet> {
         Ok(self.building()?.0.sigset.clone())
     }
+
+    /// The number of Bitcoin blocks mined since `index`'s checkpoint
+    /// transaction was confirmed, or `None` if it hasn't confirmed yet
+    /// (including if the checkpoint itself hasn't completed).
+    #[query]
+    pub fn confirmations(&self, index: u32, headers: &HeaderQueue) -> Result<Option<u32>> {
+        let checkpoint = self.get(index)?;
+        if !matches!(checkpoint.status, CheckpointStatus::Complete) {
+            return Ok(None);
+        }
+
+        let txid = checkpoint.txid()?;
+        let confirmed_height = match headers.height_of(&txid)? {
+            Some(height) => height,
+            None => return Ok(None),
+        };
+
+        let tip_height = headers.height()?;
+        Ok(Some(tip_height.saturating_sub(confirmed_height)))
+    }
+}
+
+fn verify_sig(pubkey: &Pubkey, sig: &Signature, sighash: &Adapter<Sighash>) -> Result<()> {
+    let secp = bitcoin::secp256k1::Secp256k1::verification_only();
+    let msg = bitcoin::secp256k1::Message::from_slice(sighash.as_inner())
+        .map_err(|e| Error::Orga(OrgaError::App(e.to_string())))?;
+
+    secp.verify_ecdsa(&msg, sig, pubkey).map_err(|_| {
+        Error::Orga(OrgaError::App(
+            "Signature does not match claimed sighash".to_string(),
+        ))
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+
+    fn test_pubkey(seed: u8) -> Pubkey {
+        let secp = Secp256k1::new();
+        let sk = SecretKey::from_slice(&[seed; 32]).unwrap();
+        Pubkey::from_secret_key(&secp, &sk)
+    }
+
+    fn test_sighash(seed: u8) -> Adapter<Sighash> {
+        Adapter::new(Sighash::hash(&[seed]))
+    }
+
+    #[test]
+    fn divergent_sighashes_excludes_canonical() {
+        let mut checkpoint = Checkpoint::default();
+        let canonical = test_sighash(1);
+        let other = test_sighash(2);
+
+        checkpoint
+            .record_endorsement(test_pubkey(1), canonical.clone(), 10)
+            .unwrap();
+        checkpoint
+            .record_endorsement(test_pubkey(2), other.clone(), 20)
+            .unwrap();
+
+        let divergence = checkpoint.divergent_sighashes(&canonical).unwrap();
+        assert_eq!(divergence.len(), 1);
+        assert_eq!(divergence[0].0, other);
+        assert_eq!(divergence[0].1, 20);
+    }
+
+    #[test]
+    fn divergent_sighashes_dedupes_after_vote_switch() {
+        let mut checkpoint = Checkpoint::default();
+        let canonical = test_sighash(1);
+        let other = test_sighash(2);
+        let pubkey = test_pubkey(1);
+
+        // The signatory first endorses `other`, then switches to the
+        // canonical sighash - their power should move with them, leaving
+        // nothing backing `other` any more.
+        checkpoint
+            .record_endorsement(pubkey.clone(), other.clone(), 10)
+            .unwrap();
+        checkpoint
+            .record_endorsement(pubkey, canonical.clone(), 10)
+            .unwrap();
+
+        let divergence = checkpoint.divergent_sighashes(&canonical).unwrap();
+        assert!(divergence.is_empty());
+    }
+
+    #[test]
+    fn record_endorsement_is_idempotent_for_same_vote() {
+        let mut checkpoint = Checkpoint::default();
+        let sighash = test_sighash(1);
+        let pubkey = test_pubkey(1);
+
+        checkpoint
+            .record_endorsement(pubkey.clone(), sighash.clone(), 10)
+            .unwrap();
+        checkpoint
+            .record_endorsement(pubkey.clone(), sighash.clone(), 10)
+            .unwrap();
+
+        let divergence = checkpoint.divergent_sighashes(&test_sighash(99)).unwrap();
+        assert_eq!(divergence.len(), 1);
+        assert_eq!(divergence[0].1, 10);
+        assert_eq!(divergence[0].2, vec![pubkey]);
+    }
+
+    fn test_queue() -> CheckpointQueue {
+        CheckpointQueue {
+            queue: Deque::default(),
+            index: 0,
+            checkpoint_interval: 0,
+            signing_timeout: 0,
+            divergence_quorum_percent: 0,
+        }
+    }
+
+    #[test]
+    fn signing_progress_is_none_without_a_signing_checkpoint() {
+        let mut queue = test_queue();
+        queue.queue.push_back(Checkpoint::default()).unwrap();
+
+        assert!(queue.signing_progress().unwrap().is_none());
+    }
+
+    /// Builds a `SignatorySet` directly from `(pubkey, voting_power)` pairs,
+    /// bypassing `from_validator_ctx`'s validator-context lookup so tests
+    /// can exercise a populated signatory set without a running chain.
+    fn test_sigset(entries: Vec<(Pubkey, u64)>) -> SignatorySet {
+        SignatorySet {
+            signatories: entries
+                .into_iter()
+                .map(|(pubkey, voting_power)| Signatory {
+                    pubkey,
+                    voting_power,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn signing_progress_reports_partial_signatures() {
+        let secp = Secp256k1::new();
+        let sk1 = SecretKey::from_slice(&[1; 32]).unwrap();
+        let sk2 = SecretKey::from_slice(&[2; 32]).unwrap();
+        let pk1 = Pubkey::from_secret_key(&secp, &sk1);
+        let pk2 = Pubkey::from_secret_key(&secp, &sk2);
+
+        let sigset = test_sigset(vec![(pk1.clone(), 60), (pk2.clone(), 40)]);
+
+        let mut signing = Checkpoint::default();
+        signing.status = CheckpointStatus::Signing;
+        signing.sig.set_up(sigset.clone()).unwrap();
+        signing.sigset = sigset;
+
+        let msg = bitcoin::secp256k1::Message::from_slice(&[3; 32]).unwrap();
+        let sig1 = secp.sign_ecdsa(&msg, &sk1);
+        signing.sig.sign(pk1, sig1).unwrap();
+
+        let mut queue = test_queue();
+        queue.queue.push_back(signing).unwrap();
+        queue.queue.push_back(Checkpoint::default()).unwrap();
+        queue.index = 1;
+
+        let progress = queue.signing_progress().unwrap().unwrap();
+        assert_eq!(progress.signed_voting_power, 60);
+        assert_eq!(progress.total_voting_power, 100);
+        assert_eq!(progress.quorum_voting_power, 70);
+        assert_eq!(progress.remaining_signatories, vec![pk2]);
+    }
+
+    #[test]
+    fn start_signing_does_not_advance_while_already_signing() {
+        let mut queue = test_queue();
+
+        let mut signing = Checkpoint::default();
+        signing.status = CheckpointStatus::Signing;
+        queue.queue.push_back(signing).unwrap();
+        queue.queue.push_back(Checkpoint::default()).unwrap();
+        queue.index = 1;
+
+        let sig_keys = Map::default();
+        queue.start_signing(&sig_keys).unwrap();
+
+        // There must never be more than one checkpoint `Signing` at a
+        // time, so the still-building checkpoint should be untouched.
+        assert!(matches!(
+            queue.building().unwrap().0.status,
+            CheckpointStatus::Building
+        ));
+    }
+
+    #[test]
+    fn start_signing_advances_building_to_signing_after_interval() {
+        Context::add(Time::from_seconds(2_000));
+
+        let mut queue = test_queue();
+        queue.checkpoint_interval = 100;
+
+        let mut building = Checkpoint::default();
+        building.created_at = 1_000;
+        building
+            .outputs
+            .push_back(Output {
+                amount: 1_000,
+                script: vec![],
+            })
+            .unwrap();
+        queue.queue.push_back(building).unwrap();
+        queue.index = 0;
+
+        let sig_keys = Map::default();
+        queue.start_signing(&sig_keys).unwrap();
+
+        assert_eq!(queue.queue.len(), 2);
+
+        let advanced = queue.get(0).unwrap();
+        assert!(matches!(advanced.status, CheckpointStatus::Signing));
+        assert_eq!(advanced.signing_started_at, 2_000);
+
+        assert!(matches!(
+            queue.building().unwrap().0.status,
+            CheckpointStatus::Building
+        ));
+    }
+
+    #[test]
+    fn confirmations_is_none_for_incomplete_checkpoint() {
+        let mut queue = test_queue();
+        queue.queue.push_back(Checkpoint::default()).unwrap();
+
+        let headers = HeaderQueue::default();
+        assert_eq!(queue.confirmations(0, &headers).unwrap(), None);
+    }
+
+    // NOTE: the `tip_height.saturating_sub(confirmed_height)` arithmetic
+    // for a *completed* checkpoint isn't covered here, since exercising it
+    // needs a `HeaderQueue` populated with a real, PoW-valid header chain,
+    // and these tests have no helper for building one.
+
+    #[test]
+    fn completed_skips_timed_out_checkpoints() {
+        let mut queue = test_queue();
+
+        let mut first = Checkpoint::default();
+        first.status = CheckpointStatus::Complete;
+        queue.queue.push_back(first).unwrap();
+
+        let mut timed_out = Checkpoint::default();
+        timed_out.status = CheckpointStatus::TimedOut;
+        queue.queue.push_back(timed_out).unwrap();
+
+        let mut last = Checkpoint::default();
+        last.status = CheckpointStatus::Complete;
+        queue.queue.push_back(last).unwrap();
+
+        queue.index = 2;
+
+        // A resolved `TimedOut` checkpoint sits between two completed
+        // ones, but it must not hide the later completion from callers.
+        assert_eq!(queue.completed().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn carry_over_signatures_is_empty_for_an_unset_threshold_sig() {
+        let carried = carry_over_signatures(&ThresholdSig::default(), &SignatorySet::default());
+        assert_eq!(carried.unwrap(), vec![]);
+    }
+
+    #[test]
+    fn time_out_signing_preserves_queue_bookkeeping_across_restart() {
+        Context::add(Time::from_seconds(1_000));
+
+        let mut queue = test_queue();
+        queue.signing_timeout = 10;
+
+        let mut signing = Checkpoint::default();
+        signing.status = CheckpointStatus::Signing;
+        signing.signing_started_at = 0;
+        signing
+            .outputs
+            .push_back(Output {
+                amount: 1_000,
+                script: vec![],
+            })
+            .unwrap();
+        queue.queue.push_back(signing).unwrap();
+        queue.queue.push_back(Checkpoint::default()).unwrap();
+        queue.index = 1;
+
+        let sig_keys = Map::default();
+        assert!(queue.maybe_time_out_signing(&sig_keys).unwrap());
+
+        // The stuck attempt, its restarted replacement, and the
+        // still-building checkpoint must all remain individually
+        // reachable at distinct indices - a bug in the pop/push bookkeeping
+        // would otherwise shift or clobber one of them.
+        assert_eq!(queue.queue.len(), 3);
+        assert_eq!(queue.index, 2);
+
+        let timed_out = queue.get(0).unwrap();
+        assert!(matches!(timed_out.status, CheckpointStatus::TimedOut));
+        assert_eq!(timed_out.outputs.len(), 1);
+
+        let restarted = queue.get(1).unwrap();
+        assert!(matches!(restarted.status, CheckpointStatus::Signing));
+        assert_eq!(restarted.outputs.len(), 1);
+
+        let building = queue.building().unwrap();
+        assert!(matches!(building.0.status, CheckpointStatus::Building));
+    }
 }